@@ -0,0 +1,81 @@
+use rng::{CodecRng, CoreRng};
+
+/// How a `Channel` decides whether a droplet makes it through.
+pub enum LossModel {
+    /// Every droplet is dropped independently with probability `loss`.
+    Independent { loss: f32 },
+    /// A two-state Gilbert-Elliott burst model: losses in the `Good` state
+    /// happen at `loss_in_good`, losses in the `Bad` state at
+    /// `loss_in_bad`, and the channel switches state before each droplet
+    /// with probability `p_good_to_bad` / `p_bad_to_good`.
+    GilbertElliott {
+        p_good_to_bad: f32,
+        p_bad_to_good: f32,
+        loss_in_good: f32,
+        loss_in_bad: f32
+    }
+}
+
+enum GeState {
+    Good,
+    Bad
+}
+
+/// Wraps a droplet `Iterator` (typically an `Encoder` or `ObjectEncoder`)
+/// and yields only the droplets that survive a configurable, seeded loss
+/// model, so callers can benchmark overhead under realistic, reproducible
+/// packet loss instead of only uniform i.i.d. loss.
+pub struct Channel<I> {
+    inner: I,
+    model: LossModel,
+    state: GeState,
+    rng: CoreRng
+}
+
+impl<I: Iterator> Channel<I> {
+    pub fn new(inner: I, model: LossModel, seed: usize) -> Channel<I> {
+        Channel { inner: inner, model: model, state: GeState::Good, rng: CoreRng::seeded(seed) }
+    }
+
+    fn delivered(&mut self) -> bool {
+        match self.model {
+            LossModel::Independent { loss } => self.rng.next_f32() >= loss,
+            LossModel::GilbertElliott { p_good_to_bad, p_bad_to_good, loss_in_good, loss_in_bad } => {
+                match self.state {
+                    GeState::Good => {
+                        if self.rng.next_f32() < p_good_to_bad {
+                            self.state = GeState::Bad;
+                        }
+                    }
+                    GeState::Bad => {
+                        if self.rng.next_f32() < p_bad_to_good {
+                            self.state = GeState::Good;
+                        }
+                    }
+                }
+                let loss = match self.state {
+                    GeState::Good => loss_in_good,
+                    GeState::Bad => loss_in_bad
+                };
+                self.rng.next_f32() >= loss
+            }
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Channel<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            match self.inner.next() {
+                None => return None,
+                Some(item) => {
+                    if self.delivered() {
+                        return Some(item);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,37 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(feature = "serde_support")]
+extern crate serde;
+#[cfg(feature = "serde_support")]
+#[macro_use]
+extern crate serde_derive;
+
+pub mod rng;
+pub mod mathf;
+pub mod soliton;
+pub mod ltcode;
+pub mod object;
+pub mod channel;
+
+#[cfg(feature = "std")]
 extern crate rand;
+#[cfg(feature = "std")]
 use rand::*;
 
+#[cfg(feature = "std")]
 pub struct Soliton {
     n: u32,
     rng: StdRng
 }
 
+#[cfg(feature = "std")]
 impl Soliton {
     pub fn new(n: u32, seed: usize) -> Soliton {
         let seedarr: &[_] = &[seed];
@@ -14,6 +40,7 @@ impl Soliton {
     }
 }
 
+#[cfg(feature = "std")]
 impl Iterator for Soliton {
     type Item = u32;
 
@@ -1,10 +1,33 @@
+#[cfg(feature = "std")]
 use std::vec::Vec;
-use std::cell::RefCell;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
 use std::rc::Rc;
-use std::cmp;
-use rand::{Rng, sample, StdRng, SeedableRng};
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
 
-use soliton::IdealSoliton;
+use core::cell::RefCell;
+use core::cmp;
+use core::mem;
+
+#[cfg(feature = "std")]
+extern crate rand;
+#[cfg(feature = "std")]
+use self::rand::sample;
+
+use mathf;
+use rng::{CodecRng, CoreRng, os_seed};
+use soliton::{IdealSoliton, RobustSoliton, SolitonType};
 
 #[derive(Clone, Debug)]
 pub enum EncoderType {
@@ -21,13 +44,14 @@ pub struct Encoder {
     data: Vec<u8>,
     len: usize,
     blocksize: usize,
-    rng: StdRng,
+    rng: CoreRng,
     cnt_blocks: usize,
-    sol: IdealSoliton,
+    sol: Box<Iterator<Item = usize>>,
     cnt: usize,
     encodertype: EncoderType
 }
 
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 #[derive(Debug)]
 enum DropType {
     ///First is seed, second degree
@@ -37,6 +61,7 @@ enum DropType {
 }
 
 /// A Droplet is created by the Encoder.
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 #[derive(Debug)]
 pub struct Droplet {
     /// The droptype can be based on seed or a list of edges
@@ -49,6 +74,111 @@ impl Droplet {
     fn new(droptype: DropType, data: Vec<u8>) -> Droplet {
         Droplet {droptype: droptype, data: data}
     }
+
+    /// Serializes this droplet into a compact, self-describing wire
+    /// format: one tag byte (0 = Seeded, 1 = Edges), a type-specific
+    /// header, then the payload. Lets a receiver feed the bytes straight
+    /// into `Decoder::catch` via `deserialize`, with no out-of-band
+    /// metadata needed.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self.droptype {
+            DropType::Seeded(seed, degree) => {
+                buf.push(0);
+                let seed = seed as u32;
+                buf.push((seed & 0xff) as u8);
+                buf.push(((seed >> 8) & 0xff) as u8);
+                buf.push(((seed >> 16) & 0xff) as u8);
+                buf.push(((seed >> 24) & 0xff) as u8);
+                write_varint(&mut buf, degree);
+            }
+            DropType::Edges(ref idx) => {
+                buf.push(1);
+                write_varint(&mut buf, idx.len());
+                for &i in idx {
+                    write_varint(&mut buf, i);
+                }
+            }
+        }
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+
+    /// Parses a droplet previously produced by `serialize`. Returns `None`
+    /// on a truncated or unrecognized buffer.
+    pub fn deserialize(buf: &[u8]) -> Option<Droplet> {
+        let mut pos = 0;
+        let tag = *buf.get(pos)?;
+        pos += 1;
+        let droptype = match tag {
+            0 => {
+                if buf.len() < pos + 4 {
+                    return None;
+                }
+                let seed = (buf[pos] as usize)
+                    | ((buf[pos + 1] as usize) << 8)
+                    | ((buf[pos + 2] as usize) << 16)
+                    | ((buf[pos + 3] as usize) << 24);
+                pos += 4;
+                let degree = read_varint(buf, &mut pos)?;
+                DropType::Seeded(seed, degree)
+            }
+            1 => {
+                let count = read_varint(buf, &mut pos)?;
+                // Each index takes at least one byte on the wire, so a
+                // `count` claiming more entries than the remaining buffer
+                // could hold is corrupt; reject it before allocating.
+                if count > buf.len() - pos {
+                    return None;
+                }
+                let mut idx = Vec::with_capacity(count);
+                for _ in 0..count {
+                    idx.push(read_varint(buf, &mut pos)?);
+                }
+                DropType::Edges(idx)
+            }
+            _ => return None,
+        };
+        let data = buf[pos..].to_vec();
+        Some(Droplet::new(droptype, data))
+    }
+}
+
+/// Writes `value` as a little-endian base-128 varint.
+fn write_varint(buf: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        buf.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+/// Reads a varint written by `write_varint`, advancing `pos` past it.
+/// Rejects a buffer whose continuation bytes would shift past the width of
+/// `usize`, which would otherwise panic (debug) or silently wrap (release).
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<usize> {
+    let max_shift = (mem::size_of::<usize>() * 8) as u32;
+    let mut result: usize = 0;
+    let mut shift: u32 = 0;
+    loop {
+        if shift >= max_shift {
+            return None;
+        }
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Some(result)
 }
 
 impl Encoder {
@@ -60,6 +190,12 @@ impl Encoder {
     /// The Systematic encoder first produces a set of the source symbols. After each
     /// symbol is sent once, it switches to Random.
     ///
+    /// The `soliton_type` chooses the degree distribution droplets are
+    /// sampled from. `SolitonType::Ideal` is the simplest choice;
+    /// `SolitonType::Robust` trades a little overhead for a much lower
+    /// chance that the peeling decoder stalls, which suits high-loss
+    /// channels.
+    ///
     /// The Encoder implements the iterator. You can use the iterator
     /// to produce an infinte stream of Droplets
     ///
@@ -71,41 +207,79 @@ impl Encoder {
     ///
     /// fn main() {
     ///     use fountaincode::ltcode::{Encoder, EncoderType};
+    ///     use fountaincode::soliton::SolitonType;
     ///     use self::rand::{thread_rng, Rng};
     ///
     ///     let s:String = thread_rng().gen_ascii_chars().take(1_024).collect();
     ///     let buf = s.into_bytes();
     ///
-    ///     let mut enc = Encoder::new(buf, 64, EncoderType::Random);
+    ///     let mut enc = Encoder::new(buf, 64, EncoderType::Random, SolitonType::Ideal);
     ///
     ///     for i in 1..10 {
     ///         println!("droplet {:?}: {:?}", i, enc.next());
     ///     }
     /// }
     /// ```
-    pub fn new(data: Vec<u8>, blocksize: usize, encodertype: EncoderType) -> Encoder {
-        let mut rng = StdRng::new().unwrap();
+    pub fn new(data: Vec<u8>, blocksize: usize, encodertype: EncoderType, soliton_type: SolitonType) -> Encoder {
+        Encoder::with_seed(data, blocksize, encodertype, soliton_type, os_seed())
+    }
+
+    /// Same as `new`, but takes the RNG seed explicitly instead of drawing
+    /// one from `os_seed()`.
+    ///
+    /// Under `std`, `os_seed()` pulls fresh OS entropy, so `new` is enough
+    /// on its own. Under `no_std` there is no entropy source to draw from
+    /// and `os_seed()` is a fixed placeholder -- without this constructor,
+    /// every `Encoder` built on a given device (and every `Encoder` rebuilt
+    /// after a reset) would reuse that same seed and emit a byte-identical
+    /// droplet stream forever. `no_std` callers that need distinct or
+    /// unpredictable streams should seed this from whatever entropy their
+    /// platform has (a hardware RNG, a counter, a MAC address, ...).
+    pub fn with_seed(data: Vec<u8>, blocksize: usize, encodertype: EncoderType, soliton_type: SolitonType, seed: usize) -> Encoder {
+        let mut rng = CoreRng::seeded(seed);
 
         let len = data.len();
-        let cnt_blocks = ((len as f32)/blocksize as f32).ceil() as usize;
-        let sol = IdealSoliton::new(cnt_blocks, rng.gen::<usize>());
+        let cnt_blocks = mathf::ceil((len as f32) / blocksize as f32) as usize;
+        let sol: Box<Iterator<Item = usize>> = match soliton_type {
+            SolitonType::Ideal => Box::new(IdealSoliton::new(cnt_blocks, rng.next_usize())),
+            SolitonType::Robust { c, delta } => Box::new(RobustSoliton::new(cnt_blocks, rng.next_usize(), c, delta)),
+        };
         Encoder{data: data, len: len, blocksize: blocksize, rng: rng, cnt_blocks: cnt_blocks, sol: sol, cnt: 0, encodertype: encodertype}
     }
 }
 
+#[cfg(feature = "std")]
 fn get_sample_from_rng_by_seed(seed: usize, n: usize, degree: usize) -> Vec<usize> {
-    let seedarr: &[_] = &[seed];
-    let mut rng:StdRng = SeedableRng::from_seed(seedarr);
+    let mut rng = CoreRng::seeded(seed);
     sample(&mut rng, 0..n, degree)
 }
 
+#[cfg(not(feature = "std"))]
+fn get_sample_from_rng_by_seed(seed: usize, n: usize, degree: usize) -> Vec<usize> {
+    // `rand::sample` needs `std`'s `Rng`; do the equivalent reservoir
+    // sampling (Algorithm R) by hand against our no_std PRNG instead.
+    // Clamp first: a degree above `n` (e.g. from a `Seeded` droplet read
+    // off the wire) must still select from the valid `0..n` range,
+    // matching `sample`.
+    let degree = cmp::min(degree, n);
+    let mut rng = CoreRng::seeded(seed);
+    let mut reservoir: Vec<usize> = (0..degree).collect();
+    for i in degree..n {
+        let j = rng.next_usize() % (i + 1);
+        if j < degree {
+            reservoir[j] = i;
+        }
+    }
+    reservoir
+}
+
 impl Iterator for Encoder {
     type Item = Droplet;
     fn next(&mut self) -> Option<Droplet> {
         let drop = match self.encodertype {
             EncoderType::Random => {
                 let degree = self.sol.next().unwrap() as usize; //TODO: try! macro
-                let seed = self.rng.gen::<u32>() as usize;
+                let seed = self.rng.next_u32() as usize;
                 let sample = get_sample_from_rng_by_seed(seed, self.cnt_blocks, degree);
                 let mut r:Vec<u8> = vec![0; self.blocksize];
 
@@ -151,9 +325,14 @@ pub struct Decoder {
     number_of_chunks: usize,
     cnt_received_drops: usize,
     blocks: Vec<Block>,
-    data: Vec<u8>
+    data: Vec<u8>,
+    /// Every in-range droplet we have received, kept around so that a stalled
+    /// peeling decoder can fall back to Gaussian elimination instead of
+    /// waiting for a lucky degree-one droplet.
+    rows: Vec<Rc<RefCell<RxDroplet>>>
 }
 
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 #[derive(Debug)]
 pub struct Statistics {
     pub cnt_droplets: usize,
@@ -194,6 +373,7 @@ impl Decoder {
     /// fn main() {
     ///     use fountaincode::ltcode::{Encoder, EncoderType, Decoder};
     ///     use fountaincode::ltcode::CatchResult::*;
+    ///     use fountaincode::soliton::SolitonType;
     ///     use self::rand::{thread_rng, Rng};
     ///
     ///     let s:String = thread_rng().gen_ascii_chars().take(1_024).collect();
@@ -201,7 +381,7 @@ impl Decoder {
     ///     let to_compare = buf.clone();
     ///     let length = buf.len();
     ///
-    ///     let mut enc = Encoder::new(buf, 64, EncoderType::Random);
+    ///     let mut enc = Encoder::new(buf, 64, EncoderType::Random, SolitonType::Ideal);
     ///     let mut dec = Decoder::new(length, 64);
     ///
     ///     for drop in enc {
@@ -222,7 +402,7 @@ impl Decoder {
     /// }
     /// ```
     pub fn new(len: usize, blocksize: usize) -> Decoder {
-        let number_of_chunks = ((len as f32)/blocksize as f32).ceil() as usize;
+        let number_of_chunks = mathf::ceil((len as f32) / blocksize as f32) as usize;
         let data:Vec<u8> = vec![0; number_of_chunks * blocksize];
         let mut edges:Vec<Block> = Vec::with_capacity(number_of_chunks);
         for i in 0..number_of_chunks {
@@ -236,12 +416,22 @@ impl Decoder {
                  cnt_received_drops: 0,
                  blocks: edges,
                  data: data,
-                 blocksize: blocksize }
+                 blocksize: blocksize,
+                 rows: Vec::new() }
     }
 
-    fn process_droplet(&mut self, droplet: RxDroplet) {
+    fn process_droplet(&mut self, droplet: Rc<RefCell<RxDroplet>>) {
         let mut drops:Vec<Rc<RefCell<RxDroplet>>> = Vec::new();
-        drops.push(Rc::new(RefCell::new(droplet)));
+        drops.push(droplet);
+        self.drain_drops(drops);
+    }
+
+    /// Runs the peeling cascade to a fixed point over a worklist of rows
+    /// that may have just become degree one. Shared by `process_droplet`
+    /// (seeded with the freshly caught droplet) and `try_gaussian_elimination`
+    /// (seeded with whatever `cascade_resolved_block` kicks loose), so both
+    /// paths drain `Block::edges` and strip resolved indices the same way.
+    fn drain_drops(&mut self, mut drops: Vec<Rc<RefCell<RxDroplet>>>) {
         loop { //a loop is used instead of recursion
             match drops.pop() {
                 None => return,
@@ -278,30 +468,129 @@ impl Decoder {
                             block.is_known = true;
                             self.unknown_chunks -= 1;
 
-                            while block.edges.len() > 0 {
-                                let edge = block.edges.pop().unwrap();
-                                let mut m_edge = edge.borrow_mut();
+                            self.cascade_resolved_block(first_idx, &mut drops);
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-                                if m_edge.edges_idx.len() == 1 {
-                                    drops.push(edge.clone());
-                                }
-                                else {
-                                    for i in 0..self.blocksize {
-                                        m_edge.data[i] ^= self.data[block.begin_at+i]
-                                    }
-
-                                    let pos = m_edge.edges_idx.iter().position(|x| x == &block.idx).unwrap();
-                                    m_edge.edges_idx.remove(pos);
-                                    if m_edge.edges_idx.len() == 1 {
-                                        drops.push(edge.clone());
-                                    }
-                                }
-                            }
+    /// Drains every row still waiting on a just-resolved block: XORs the
+    /// block's now-known payload out of each row's data and strips the
+    /// resolved index from its `edges_idx`, queuing any row that falls to
+    /// degree one so `drain_drops` can resolve it in turn. Must run for
+    /// *any* path that marks a block known (peeling or Gaussian
+    /// elimination) -- otherwise rows still carrying that block's
+    /// unresolved contribution get reused later with stale data.
+    fn cascade_resolved_block(&mut self, idx: usize, drops: &mut Vec<Rc<RefCell<RxDroplet>>>) {
+        let block = self.blocks.get_mut(idx).unwrap();
+        while block.edges.len() > 0 {
+            let edge = block.edges.pop().unwrap();
+            let mut m_edge = edge.borrow_mut();
+
+            if m_edge.edges_idx.len() == 1 {
+                drops.push(edge.clone());
+            }
+            else {
+                for i in 0..self.blocksize {
+                    m_edge.data[i] ^= self.data[block.begin_at+i]
+                }
+
+                let pos = m_edge.edges_idx.iter().position(|x| x == &block.idx).unwrap();
+                m_edge.edges_idx.remove(pos);
+                if m_edge.edges_idx.len() == 1 {
+                    drops.push(edge.clone());
+                }
+            }
+        }
+    }
+
+    /// Assembles every still-outstanding droplet into a binary coefficient
+    /// matrix over the unknown blocks and runs Gaussian elimination,
+    /// XOR-combining the payloads in lockstep with the row operations.
+    /// Called from `catch` once peeling has stalled; recovers every block
+    /// the received droplets algebraically determine, even if none of them
+    /// ever reduces to degree one on its own.
+    fn try_gaussian_elimination(&mut self) {
+        if self.unknown_chunks == 0 {
+            return;
+        }
+
+        let unknown_idx: Vec<usize> = self.blocks.iter().filter(|b| !b.is_known).map(|b| b.idx).collect();
+        let col_of: BTreeMap<usize, usize> = unknown_idx.iter().enumerate().map(|(pos, &idx)| (idx, pos)).collect();
+        let cols_n = unknown_idx.len();
+
+        let mut matrix: Vec<Vec<bool>> = Vec::new();
+        let mut payload: Vec<Vec<u8>> = Vec::new();
+        for row in &self.rows {
+            let r = row.borrow();
+            let mut cols = vec![false; cols_n];
+            let mut any = false;
+            for ed in &r.edges_idx {
+                if let Some(&pos) = col_of.get(ed) {
+                    cols[pos] = true;
+                    any = true;
+                }
+            }
+            if any {
+                matrix.push(cols);
+                payload.push(r.data.clone());
+            }
+        }
+
+        let rows_n = matrix.len();
+        let mut pivot_row_of_col: Vec<Option<usize>> = vec![None; cols_n];
+        let mut pivot = 0;
+        for col in 0..cols_n {
+            if pivot >= rows_n {
+                break;
+            }
+            let found = (pivot..rows_n).find(|&r| matrix[r][col]);
+            if let Some(sel) = found {
+                matrix.swap(pivot, sel);
+                payload.swap(pivot, sel);
+                for r in 0..rows_n {
+                    if r != pivot && matrix[r][col] {
+                        for c in 0..cols_n {
+                            matrix[r][c] ^= matrix[pivot][c];
+                        }
+                        for i in 0..self.blocksize {
+                            payload[r][i] ^= payload[pivot][i];
+                        }
+                    }
+                }
+                pivot_row_of_col[col] = Some(pivot);
+                pivot += 1;
+            }
+        }
+
+        let mut drops: Vec<Rc<RefCell<RxDroplet>>> = Vec::new();
+        for col in 0..cols_n {
+            if let Some(r) = pivot_row_of_col[col] {
+                let is_solved = matrix[r].iter().enumerate().all(|(c, &v)| v == (c == col));
+                if is_solved {
+                    let idx = unknown_idx[col];
+                    {
+                        let block = self.blocks.get_mut(idx).unwrap();
+                        for i in 0..self.blocksize {
+                            self.data[block.begin_at+i] = payload[r][i];
                         }
+                        block.is_known = true;
                     }
+                    self.unknown_chunks -= 1;
+
+                    // Same obligation as the peeling cascade: every other
+                    // row still referencing this block must have its
+                    // contribution XORed out and the index stripped, not
+                    // just this column's own payload recorded.
+                    self.cascade_resolved_block(idx, &mut drops);
                 }
             }
         }
+        if !drops.is_empty() {
+            self.drain_drops(drops);
+        }
     }
 
     /// Catches a Droplet
@@ -315,8 +604,27 @@ impl Decoder {
             DropType::Edges(edges) => {edges}
         };
 
-        let rxdrop = RxDroplet {edges_idx: sample, data: drop.data};
-        self.process_droplet(rxdrop);
+        // A droplet naming a block index outside the known range can only
+        // come from corrupt or malicious input (e.g. over the wire via
+        // `Droplet::deserialize`); drop it rather than panic on the
+        // `self.blocks.get_mut(ed).unwrap()` lookups below.
+        if sample.iter().all(|&idx| idx < self.number_of_chunks) {
+            let rxdrop = Rc::new(RefCell::new(RxDroplet {edges_idx: sample, data: drop.data}));
+            self.rows.push(rxdrop.clone());
+            let unknown_before = self.unknown_chunks;
+            self.process_droplet(rxdrop);
+
+            // Only pay for Gaussian elimination once peeling has actually
+            // stalled (this droplet resolved nothing on its own); otherwise
+            // every droplet past `number_of_chunks` would re-reduce the
+            // whole matrix even while peeling keeps making progress alone.
+            if self.unknown_chunks > 0
+                && self.unknown_chunks == unknown_before
+                && self.cnt_received_drops >= self.number_of_chunks {
+                self.try_gaussian_elimination();
+            }
+        }
+
         let stats = Statistics {
             cnt_droplets: self.cnt_received_drops,
             cnt_chunks: self.number_of_chunks,
@@ -1,19 +1,51 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+
+#[cfg(feature = "std")]
 extern crate rand;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(feature = "serde_support")]
+extern crate serde;
+#[cfg(feature = "serde_support")]
+#[macro_use]
+extern crate serde_derive;
 
 #[macro_use]
 extern crate log;
+#[cfg(feature = "std")]
 extern crate env_logger;
 
+pub mod rng;
+pub mod mathf;
 pub mod soliton;
 pub mod ltcode;
+pub mod object;
+pub mod channel;
+
+#[cfg(feature = "std")]
 use ltcode::Encoder;
+#[cfg(feature = "std")]
+use ltcode::EncoderType;
+#[cfg(feature = "std")]
 use ltcode::Decoder;
+#[cfg(feature = "std")]
 use ltcode::CatchResult::*;
+#[cfg(feature = "std")]
+use soliton::SolitonType;
 
+#[cfg(feature = "std")]
 use std::io::Error;
+#[cfg(feature = "std")]
 use std::fs::File;
+#[cfg(feature = "std")]
 use std::io::prelude::*;
 
+#[cfg(feature = "std")]
 fn main() {
     env_logger::init().unwrap();
     for _ in 0..1 {
@@ -21,6 +53,12 @@ fn main() {
     }
 }
 
+/// Nothing to run without `std`: this binary only exists to exercise the
+/// codec against a file on disk, which `no_std` targets don't have.
+#[cfg(not(feature = "std"))]
+fn main() {}
+
+#[cfg(feature = "std")]
 fn test_fountain() -> Result<(), Error> {
     let mut buf = Vec::new();
     let mut f = File::open("testfile.bin").unwrap();
@@ -29,7 +67,7 @@ fn test_fountain() -> Result<(), Error> {
     debug!("len: {:?}", length);
     let buf_org = buf.clone();
 
-    let mut enc = Encoder::new(buf, 1024);
+    let mut enc = Encoder::new(buf, 1024, EncoderType::Random, SolitonType::Ideal);
     let mut dec = Decoder::new(length, 1024);
 
     let mut done = false;
@@ -0,0 +1,59 @@
+//! `f32` operations the codec needs, usable the same way whether or not
+//! `std` is available. With `std` these just forward to the inherent
+//! methods; without it, those methods need `libm` and aren't available on
+//! bare `core::f32`, so we use small self-contained substitutes instead.
+
+#[cfg(feature = "std")]
+pub fn ceil(x: f32) -> f32 {
+    x.ceil()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn ceil(x: f32) -> f32 {
+    let truncated = x as i32 as f32;
+    if x > truncated { truncated + 1.0 } else { truncated }
+}
+
+#[cfg(feature = "std")]
+pub fn floor(x: f32) -> f32 {
+    x.floor()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn floor(x: f32) -> f32 {
+    let truncated = x as i32 as f32;
+    if x < truncated { truncated - 1.0 } else { truncated }
+}
+
+#[cfg(feature = "std")]
+pub fn sqrt(x: f32) -> f32 {
+    x.sqrt()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn sqrt(x: f32) -> f32 {
+    // Newton-Raphson; a fixed, generous iteration count converges for any
+    // sane input without needing a clever initial guess.
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut guess = x;
+    for _ in 0..24 {
+        guess = 0.5 * (guess + x / guess);
+    }
+    guess
+}
+
+#[cfg(feature = "std")]
+pub fn ln(x: f32) -> f32 {
+    x.ln()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn ln(x: f32) -> f32 {
+    // Classic bit-reinterpretation approximation of log2, converted to a
+    // natural log. A few percent of error is fine for tuning the robust
+    // soliton's spike function.
+    let log2 = (x.to_bits() as f32) * 1.192_092_9e-7 - 126.942_695;
+    log2 * core::f32::consts::LN_2
+}
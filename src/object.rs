@@ -0,0 +1,168 @@
+#[cfg(feature = "std")]
+use std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::cmp;
+
+use mathf;
+use ltcode::{CatchResult, Decoder, Droplet, Encoder, EncoderType};
+use rng::os_seed;
+use soliton::SolitonType;
+
+/// Splits `total_chunks` chunks across `num_blocks` source blocks as evenly
+/// as possible, mirroring RaptorQ's partitioning scheme. Returns
+/// `(long_block_count, long_block_size, short_block_count, short_block_size)`,
+/// sizes in chunks, so block boundaries are deterministic on both the
+/// `ObjectEncoder` and `ObjectDecoder` side.
+///
+/// `num_blocks` must be at least 1; it is a caller-chosen constant, not
+/// data read off the wire, so a `0` here is a programming error rather
+/// than something to recover from.
+pub fn partition(total_chunks: usize, num_blocks: usize) -> (usize, usize, usize, usize) {
+    assert!(num_blocks > 0, "partition: num_blocks must be at least 1");
+    let base = total_chunks / num_blocks;
+    let remainder = total_chunks % num_blocks;
+    (remainder, base + 1, num_blocks - remainder, base)
+}
+
+/// A Droplet tagged with the source block it belongs to.
+#[derive(Debug)]
+pub struct ObjectDroplet {
+    pub block_id: usize,
+    pub droplet: Droplet
+}
+
+/// Encodes a large object by partitioning it into independent source
+/// blocks, each driven by its own `Encoder` (own soliton distribution and
+/// seed). This keeps degree sampling and XOR fan-out cheap per block
+/// regardless of the object's total size.
+pub struct ObjectEncoder {
+    encoders: Vec<Encoder>,
+    cnt: usize
+}
+
+impl ObjectEncoder {
+    pub fn new(data: Vec<u8>, blocksize: usize, num_blocks: usize, encodertype: EncoderType, soliton_type: SolitonType) -> ObjectEncoder {
+        ObjectEncoder::with_seed(data, blocksize, num_blocks, encodertype, soliton_type, os_seed())
+    }
+
+    /// Same as `new`, but takes the base RNG seed explicitly instead of
+    /// drawing one from `os_seed()`. Each per-block `Encoder` is seeded
+    /// from a value derived from `seed` and the block's index, so blocks
+    /// never share a seed even under `no_std`, where `os_seed()` is a
+    /// fixed placeholder rather than real entropy -- see
+    /// `Encoder::with_seed` for why that matters there.
+    pub fn with_seed(data: Vec<u8>, blocksize: usize, num_blocks: usize, encodertype: EncoderType, soliton_type: SolitonType, seed: usize) -> ObjectEncoder {
+        let total_len = data.len();
+        let total_chunks = mathf::ceil((total_len as f32) / blocksize as f32) as usize;
+        let (long_cnt, long_size, _short_cnt, short_size) = partition(total_chunks, num_blocks);
+
+        let mut encoders = Vec::with_capacity(num_blocks);
+        let mut begin = 0;
+        for i in 0..num_blocks {
+            let block_chunks = if i < long_cnt { long_size } else { short_size };
+            let end = cmp::min(begin + block_chunks * blocksize, total_len);
+            let slice = data[begin..end].to_vec();
+            let block_seed = seed.wrapping_add(i).wrapping_mul(0x9E37_79B9);
+            encoders.push(Encoder::with_seed(slice, blocksize, encodertype.clone(), soliton_type.clone(), block_seed));
+            begin = end;
+        }
+
+        ObjectEncoder { encoders: encoders, cnt: 0 }
+    }
+}
+
+impl Iterator for ObjectEncoder {
+    type Item = ObjectDroplet;
+
+    fn next(&mut self) -> Option<ObjectDroplet> {
+        let block_id = self.cnt % self.encoders.len();
+        self.cnt += 1;
+        let droplet = self.encoders[block_id].next().unwrap();
+        Some(ObjectDroplet { block_id: block_id, droplet: droplet })
+    }
+}
+
+#[derive(Debug)]
+pub struct ObjectStatistics {
+    pub cnt_droplets: usize,
+    pub cnt_blocks: usize,
+    pub finished_blocks: usize
+}
+
+#[derive(Debug)]
+pub enum ObjectCatchResult {
+    Finished(Vec<u8>),
+    Missing(ObjectStatistics)
+}
+
+/// Decodes a large object by routing each incoming `ObjectDroplet` to the
+/// `Decoder` for its source block, and reassembling the object once every
+/// block has finished. Bounds per-block decoding cost and lets a receiver
+/// start decoding block-by-block instead of waiting on the whole object.
+pub struct ObjectDecoder {
+    decoders: Vec<Decoder>,
+    results: Vec<Option<Vec<u8>>>,
+    finished_blocks: usize,
+    cnt_received: usize
+}
+
+impl ObjectDecoder {
+    pub fn new(total_len: usize, blocksize: usize, num_blocks: usize) -> ObjectDecoder {
+        let total_chunks = mathf::ceil((total_len as f32) / blocksize as f32) as usize;
+        let (long_cnt, long_size, _short_cnt, short_size) = partition(total_chunks, num_blocks);
+
+        let mut decoders = Vec::with_capacity(num_blocks);
+        let mut begin = 0;
+        for i in 0..num_blocks {
+            let block_chunks = if i < long_cnt { long_size } else { short_size };
+            let end = cmp::min(begin + block_chunks * blocksize, total_len);
+            decoders.push(Decoder::new(end - begin, blocksize));
+            begin = end;
+        }
+
+        ObjectDecoder {
+            decoders: decoders,
+            results: vec![None; num_blocks],
+            finished_blocks: 0,
+            cnt_received: 0
+        }
+    }
+
+    pub fn catch(&mut self, od: ObjectDroplet) -> ObjectCatchResult {
+        self.cnt_received += 1;
+
+        // A `block_id` outside the partitioned range can only come from
+        // corrupt or malicious input; reject it before indexing rather than
+        // panicking, mirroring `ltcode::Decoder::catch`'s edge validation.
+        if od.block_id >= self.decoders.len() {
+            return ObjectCatchResult::Missing(ObjectStatistics {
+                cnt_droplets: self.cnt_received,
+                cnt_blocks: self.decoders.len(),
+                finished_blocks: self.finished_blocks
+            });
+        }
+
+        if self.results[od.block_id].is_none() {
+            if let CatchResult::Finished(data, _stats) = self.decoders[od.block_id].catch(od.droplet) {
+                self.results[od.block_id] = Some(data);
+                self.finished_blocks += 1;
+            }
+        }
+
+        if self.finished_blocks == self.decoders.len() {
+            let mut object = Vec::new();
+            for block in &self.results {
+                object.extend_from_slice(block.as_ref().unwrap());
+            }
+            ObjectCatchResult::Finished(object)
+        } else {
+            ObjectCatchResult::Missing(ObjectStatistics {
+                cnt_droplets: self.cnt_received,
+                cnt_blocks: self.decoders.len(),
+                finished_blocks: self.finished_blocks
+            })
+        }
+    }
+}
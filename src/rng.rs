@@ -0,0 +1,97 @@
+//! A seedable RNG that the rest of the crate can use the same way whether
+//! or not `std` is available.
+//!
+//! With the `std` feature this is just `rand::StdRng`. Without it, `rand`'s
+//! OS-seeded generators aren't usable, so we fall back to `rand_xorshift`,
+//! a `rand_core`-based PRNG that only needs `core`.
+
+#[cfg(feature = "std")]
+extern crate rand;
+
+#[cfg(feature = "std")]
+use self::rand::{Rng, SeedableRng, StdRng};
+
+#[cfg(not(feature = "std"))]
+extern crate rand_core;
+#[cfg(not(feature = "std"))]
+extern crate rand_xorshift;
+
+#[cfg(not(feature = "std"))]
+use self::rand_core::{Rng, SeedableRng};
+#[cfg(not(feature = "std"))]
+use self::rand_xorshift::XorShiftRng;
+
+/// Operations the codec needs from a seeded RNG, implemented both for
+/// `rand::StdRng` (`std`) and for `CoreRng`'s `XorShiftRng` wrapper (no `std`).
+pub trait CodecRng {
+    fn seeded(seed: usize) -> Self;
+    fn next_u32(&mut self) -> u32;
+    fn next_usize(&mut self) -> usize;
+    fn next_f32(&mut self) -> f32;
+}
+
+#[cfg(feature = "std")]
+pub type CoreRng = StdRng;
+
+#[cfg(feature = "std")]
+impl CodecRng for CoreRng {
+    fn seeded(seed: usize) -> CoreRng {
+        let seedarr: &[_] = &[seed];
+        SeedableRng::from_seed(seedarr)
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.gen::<u32>()
+    }
+
+    fn next_usize(&mut self) -> usize {
+        self.gen::<usize>()
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.gen::<f32>()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub struct CoreRng {
+    rng: XorShiftRng,
+}
+
+#[cfg(not(feature = "std"))]
+impl CodecRng for CoreRng {
+    fn seeded(seed: usize) -> CoreRng {
+        CoreRng { rng: XorShiftRng::seed_from_u64(seed as u64) }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_usize(&mut self) -> usize {
+        self.rng.next_u64() as usize
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// A fresh seed for a top-level `Encoder`/`ObjectEncoder` RNG.
+#[cfg(feature = "std")]
+pub fn os_seed() -> usize {
+    StdRng::new().unwrap().gen::<usize>()
+}
+
+/// Without `std` there is no OS entropy source to draw a fresh seed from,
+/// so this is a fixed placeholder rather than real randomness: every
+/// `Encoder`/`ObjectEncoder` built from it reuses the same seed. It exists
+/// only to keep `Encoder::new`/`ObjectEncoder::new` callable the same way
+/// across both builds; `no_std` callers that need distinct or
+/// unpredictable droplet streams must use `Encoder::with_seed`/
+/// `ObjectEncoder::with_seed` with a seed drawn from their own platform's
+/// entropy (a hardware RNG, a counter, a MAC address, ...).
+#[cfg(not(feature = "std"))]
+pub fn os_seed() -> usize {
+    0x5DEE_CE66
+}
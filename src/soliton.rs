@@ -1,18 +1,23 @@
-extern crate rand;
-use rand::*;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::cmp;
+
+use mathf;
+use rng::{CodecRng, CoreRng};
 
 pub struct IdealSoliton {
     limit: f32,
-    rng: StdRng,
+    rng: CoreRng,
 }
 
 impl IdealSoliton {
     pub fn new(k: usize, seed: usize) -> IdealSoliton {
-        let seedarr: &[_] = &[seed];
-        let rng: StdRng = SeedableRng::from_seed(seedarr);
         IdealSoliton {
             limit: 1.0 / (k as f32),
-            rng: rng,
+            rng: CoreRng::seeded(seed),
         }
     }
 }
@@ -21,12 +26,89 @@ impl Iterator for IdealSoliton {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        let y = self.rng.gen::<f32>();
+        let y = self.rng.next_f32();
         if y >= self.limit {
-            let res = (1.0 / y).ceil() as usize;
+            let res = mathf::ceil(1.0 / y) as usize;
             Some(res)
         } else {
             Some(1)
         }
     }
 }
+
+/// Selects which degree distribution an `Encoder` samples from.
+#[derive(Clone, Debug)]
+pub enum SolitonType {
+    /// Pure ideal soliton distribution. Simple, but leaves a non-trivial
+    /// probability that the peeling decoder stalls, forcing high overhead.
+    Ideal,
+    /// Ideal soliton plus Luby's spike function, tuned by `c` and `delta`.
+    /// Costs a little extra overhead in exchange for a much lower chance
+    /// that decoding stalls, which suits high-loss channels.
+    Robust { c: f32, delta: f32 },
+}
+
+/// Robust Soliton distribution, as described by Luby (2002): the ideal
+/// soliton spectrum plus a spike function `tau` that guarantees enough
+/// low-degree droplets are in flight for the peeling decoder to finish
+/// close to the information-theoretic minimum number of droplets.
+pub struct RobustSoliton {
+    cdf: Vec<f32>,
+    rng: CoreRng,
+}
+
+impl RobustSoliton {
+    /// Builds the cumulative distribution for `k` source blocks once, so
+    /// `next` only has to draw a uniform value and binary-search it.
+    pub fn new(k: usize, seed: usize, c: f32, delta: f32) -> RobustSoliton {
+        let rng = CoreRng::seeded(seed);
+
+        let k_f = k as f32;
+        let r = c * mathf::ln(k_f / delta) * mathf::sqrt(k_f);
+        let spike = mathf::floor(k_f / r) as usize;
+
+        let mut mu = vec![0.0f32; k + 1]; // index 0 is unused, degrees run 1..=k
+        for i in 1..=k {
+            let rho = if i == 1 {
+                1.0 / k_f
+            } else {
+                1.0 / ((i as f32) * (i as f32 - 1.0))
+            };
+            let tau = if i < spike {
+                r / ((i as f32) * k_f)
+            } else if i == spike {
+                r * mathf::ln(r / delta) / k_f
+            } else {
+                0.0
+            };
+            mu[i] = rho + tau;
+        }
+
+        let beta: f32 = mu.iter().sum();
+        let mut cdf = vec![0.0f32; k + 1];
+        let mut acc = 0.0f32;
+        for i in 1..=k {
+            acc += mu[i] / beta;
+            cdf[i] = acc;
+        }
+
+        RobustSoliton { cdf: cdf, rng: rng }
+    }
+}
+
+impl Iterator for RobustSoliton {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let y = self.rng.next_f32();
+        let degree = match self.cdf.binary_search_by(|probe| probe.partial_cmp(&y).unwrap()) {
+            Ok(i) => i,
+            Err(i) => i,
+        };
+        // `cdf`'s last entry is only an f32 approximation of 1.0, so a draw
+        // above that gap would otherwise return `cdf.len()` (one past the
+        // last valid degree). Clamp it back into range.
+        let degree = cmp::min(degree, self.cdf.len() - 1);
+        Some(if degree == 0 { 1 } else { degree })
+    }
+}
@@ -1,8 +1,11 @@
 extern crate rand;
 extern crate fountaincode;
 
-use self::fountaincode::ltcode::{Encoder, EncoderType, Decoder};
+use self::fountaincode::ltcode::{Encoder, EncoderType, Decoder, Droplet};
 use self::fountaincode::ltcode::CatchResult::*;
+use self::fountaincode::soliton::SolitonType;
+use self::fountaincode::channel::{Channel, LossModel};
+use self::fountaincode::object::{partition, ObjectEncoder, ObjectDecoder, ObjectCatchResult};
 
 use rand::{thread_rng, Rng};
 
@@ -13,7 +16,7 @@ fn encode_decode_random(total_len: usize, chunk_len: usize) {
     let len = buf.len();
     let to_compare = buf.clone();
 
-    let enc = Encoder::new(buf, chunk_len, EncoderType::Random);
+    let enc = Encoder::new(buf, chunk_len, EncoderType::Random, SolitonType::Ideal);
     let mut dec = Decoder::new(len, chunk_len);
 
     for drop in enc {
@@ -40,7 +43,7 @@ fn encode_decode_systematic(total_len: usize, chunk_len: usize) {
     let len = buf.len();
     let to_compare = buf.clone();
 
-    let enc = Encoder::new(buf, chunk_len, EncoderType::Systematic);
+    let enc = Encoder::new(buf, chunk_len, EncoderType::Systematic, SolitonType::Ideal);
     let mut dec = Decoder::new(len, chunk_len);
 
     let mut cnt_drops = 0;
@@ -70,27 +73,23 @@ fn encode_decode_systematic_with_loss(total_len: usize, chunk_len: usize, loss:
     let len = buf.len();
     let to_compare = buf.clone();
 
-    let enc = Encoder::new(buf, chunk_len, EncoderType::Systematic);
+    let enc = Encoder::new(buf, chunk_len, EncoderType::Systematic, SolitonType::Ideal);
     let mut dec = Decoder::new(len, chunk_len);
 
-    let mut cnt_drops = 0;
-    let mut loss_rng = thread_rng();
+    let channel = Channel::new(enc, LossModel::Independent { loss: loss }, thread_rng().gen::<usize>());
 
-    for drop in enc {
-        cnt_drops += 1;
-        if loss_rng.next_f32() > loss {
-            match dec.catch(drop) {
-                Missing(stats) => {
-                    //a systematic encoder and no loss on channel should only need k symbols
-                    //assert_eq!(stats.cnt_chunks-stats.unknown_chunks, cnt_drops)
-                }
-                Finished(data, stats) => {
-                    assert_eq!(to_compare.len(), data.len());
-                    for i in 0..len {
-                        assert_eq!(to_compare[i], data[i]);
-                    }
-                    return
+    for drop in channel {
+        match dec.catch(drop) {
+            Missing(stats) => {
+                //a systematic encoder and no loss on channel should only need k symbols
+                //assert_eq!(stats.cnt_chunks-stats.unknown_chunks, cnt_drops)
+            }
+            Finished(data, stats) => {
+                assert_eq!(to_compare.len(), data.len());
+                for i in 0..len {
+                    assert_eq!(to_compare[i], data[i]);
                 }
+                return
             }
         }
     }
@@ -141,3 +140,294 @@ fn combinations_encode_decode_with_loss_begin_with_systematic() {
         }
     }
 }
+
+fn encode_decode_robust(total_len: usize, chunk_len: usize) {
+    let s:String = thread_rng().gen_ascii_chars().take(total_len).collect();
+    let buf = s.into_bytes();
+    let len = buf.len();
+    let to_compare = buf.clone();
+
+    let soliton_type = SolitonType::Robust { c: 0.2, delta: 0.05 };
+    let enc = Encoder::new(buf, chunk_len, EncoderType::Random, soliton_type);
+    let mut dec = Decoder::new(len, chunk_len);
+
+    for drop in enc {
+        match dec.catch(drop) {
+            Missing(stats) => {
+                println!("Missing blocks {:?}", stats);
+            }
+            Finished(data, stats) => {
+                assert_eq!(to_compare.len(), data.len());
+                for i in 0..len {
+                    assert_eq!(to_compare[i], data[i]);
+                }
+                println!("Finished, stats: {:?}", stats);
+                return
+            }
+        }
+    }
+}
+
+#[test]
+fn small_test_robust_soliton_encoder() {
+    encode_decode_robust(1300, 128);
+}
+
+#[test]
+fn combinations_encode_decode_with_uneven_sizes_robust() {
+    for size in 1000..1100 {
+        for chunk in 100..130 {
+            encode_decode_robust(size, chunk);
+        }
+    }
+}
+
+#[test]
+fn droplet_serialize_deserialize_round_trip() {
+    let s:String = thread_rng().gen_ascii_chars().take(600).collect();
+    let buf = s.into_bytes();
+    let len = buf.len();
+    let to_compare = buf.clone();
+
+    let mut enc = Encoder::new(buf, 64, EncoderType::Random, SolitonType::Ideal);
+    let mut dec = Decoder::new(len, 64);
+
+    loop {
+        let drop = enc.next().unwrap();
+        let wire = drop.serialize();
+        let restored = Droplet::deserialize(&wire).expect("a droplet we just serialized must deserialize");
+        match dec.catch(restored) {
+            Missing(_) => {}
+            Finished(data, stats) => {
+                assert_eq!(to_compare.len(), data.len());
+                for i in 0..len {
+                    assert_eq!(to_compare[i], data[i]);
+                }
+                println!("Finished, stats: {:?}", stats);
+                return
+            }
+        }
+    }
+}
+
+#[test]
+fn droplet_deserialize_rejects_truncated_varint() {
+    // tag = Edges, followed by 11 bytes all carrying the continuation bit,
+    // which would otherwise shift `read_varint`'s result past the width of
+    // `usize` while decoding the edge count.
+    let mut malformed = vec![1u8];
+    malformed.extend(vec![0xffu8; 11]);
+    assert!(Droplet::deserialize(&malformed).is_none());
+}
+
+#[test]
+fn droplet_deserialize_rejects_oversized_edge_count() {
+    // tag = Edges, count claims 127 indices but the buffer has none left.
+    let buf = vec![1u8, 0x7f];
+    assert!(Droplet::deserialize(&buf).is_none());
+}
+
+/// Builds an `Edges` droplet straight from the wire format, for tests that
+/// need to hand-craft droplets `Droplet::new` (private) can't give them.
+/// Only valid for indices and counts small enough to fit a single-byte
+/// varint, which is all these tests need.
+fn edges_droplet(idx: &[usize], payload: &[u8]) -> Droplet {
+    let mut buf = vec![1u8, idx.len() as u8];
+    for &i in idx {
+        buf.push(i as u8);
+    }
+    buf.extend_from_slice(payload);
+    Droplet::deserialize(&buf).unwrap()
+}
+
+#[test]
+fn decoder_ignores_out_of_range_edges_without_panicking() {
+    let blocksize = 4;
+    let len = 8; // 2 chunks
+
+    let mut dec = Decoder::new(len, blocksize);
+    let bogus = edges_droplet(&[5], &[0, 0, 0, 0]); // no chunk 5 in a 2-chunk object
+
+    match dec.catch(bogus) {
+        Missing(stats) => assert_eq!(stats.unknown_chunks, 2),
+        Finished(..) => panic!("a malformed droplet must not resolve any chunk"),
+    }
+}
+
+#[test]
+fn gaussian_elimination_resolves_stalled_peeling() {
+    // Three chunks, three droplets, none of degree one: {0,1}, {0,2},
+    // {0,1,2}. Peeling alone can never start (no droplet ever reduces to a
+    // single edge), but the three equations are linearly independent, so
+    // Gaussian elimination can still solve for every chunk.
+    let chunk0 = vec![1u8, 2, 3, 4];
+    let chunk1 = vec![5u8, 6, 7, 8];
+    let chunk2 = vec![9u8, 10, 11, 12];
+    let blocksize = 4;
+    let len = chunk0.len() + chunk1.len() + chunk2.len();
+
+    let xor = |a: &[u8], b: &[u8]| -> Vec<u8> {
+        a.iter().zip(b.iter()).map(|(x, y)| x ^ y).collect()
+    };
+
+    let mut dec = Decoder::new(len, blocksize);
+
+    let d01 = edges_droplet(&[0, 1], &xor(&chunk0, &chunk1));
+    let d02 = edges_droplet(&[0, 2], &xor(&chunk0, &chunk2));
+    let d012 = edges_droplet(&[0, 1, 2], &xor(&xor(&chunk0, &chunk1), &chunk2));
+
+    match dec.catch(d01) {
+        Missing(stats) => assert_eq!(stats.unknown_chunks, 3),
+        Finished(..) => panic!("a single degree-two droplet can't resolve anything"),
+    }
+    match dec.catch(d02) {
+        Missing(stats) => assert_eq!(stats.unknown_chunks, 3),
+        Finished(..) => panic!("peeling has nothing to start from yet"),
+    }
+    match dec.catch(d012) {
+        Finished(data, _stats) => {
+            assert_eq!(&data[0..4], &chunk0[..]);
+            assert_eq!(&data[4..8], &chunk1[..]);
+            assert_eq!(&data[8..12], &chunk2[..]);
+        }
+        Missing(_) => panic!("Gaussian elimination should resolve this full-rank system"),
+    }
+}
+
+#[test]
+fn partition_splits_chunks_evenly() {
+    let (long_cnt, long_size, short_cnt, short_size) = partition(10, 3);
+    assert_eq!(long_cnt, 1);
+    assert_eq!(long_size, 4);
+    assert_eq!(short_cnt, 2);
+    assert_eq!(short_size, 3);
+    assert_eq!(long_cnt * long_size + short_cnt * short_size, 10);
+}
+
+#[test]
+#[should_panic]
+fn partition_rejects_zero_blocks() {
+    partition(10, 0);
+}
+
+fn encode_decode_object(total_len: usize, chunk_len: usize, num_blocks: usize) {
+    let s:String = thread_rng().gen_ascii_chars().take(total_len).collect();
+    let buf = s.into_bytes();
+    let len = buf.len();
+    let to_compare = buf.clone();
+
+    let enc = ObjectEncoder::new(buf, chunk_len, num_blocks, EncoderType::Random, SolitonType::Ideal);
+    let mut dec = ObjectDecoder::new(len, chunk_len, num_blocks);
+
+    for od in enc {
+        match dec.catch(od) {
+            ObjectCatchResult::Missing(stats) => {
+                println!("Missing blocks {:?}", stats);
+            }
+            ObjectCatchResult::Finished(data) => {
+                assert_eq!(to_compare.len(), data.len());
+                for i in 0..len {
+                    assert_eq!(to_compare[i], data[i]);
+                }
+                return
+            }
+        }
+    }
+}
+
+#[test]
+fn small_test_object_encoder_decoder() {
+    encode_decode_object(4_096, 128, 4);
+}
+
+#[test]
+fn combinations_encode_decode_object_with_uneven_sizes() {
+    for size in 4000..4100 {
+        for num_blocks in 2..5 {
+            encode_decode_object(size, 128, num_blocks);
+        }
+    }
+}
+
+fn encode_decode_systematic_with_gilbert_elliott_loss(total_len: usize, chunk_len: usize) {
+    let s:String = thread_rng().gen_ascii_chars().take(total_len).collect();
+    let buf = s.into_bytes();
+    let len = buf.len();
+    let to_compare = buf.clone();
+
+    let enc = Encoder::new(buf, chunk_len, EncoderType::Systematic, SolitonType::Ideal);
+    let mut dec = Decoder::new(len, chunk_len);
+
+    let model = LossModel::GilbertElliott {
+        p_good_to_bad: 0.05,
+        p_bad_to_good: 0.3,
+        loss_in_good: 0.05,
+        loss_in_bad: 0.6
+    };
+    let channel = Channel::new(enc, model, thread_rng().gen::<usize>());
+
+    for drop in channel {
+        match dec.catch(drop) {
+            Missing(stats) => {
+                println!("Missing blocks {:?}", stats);
+            }
+            Finished(data, stats) => {
+                assert_eq!(to_compare.len(), data.len());
+                for i in 0..len {
+                    assert_eq!(to_compare[i], data[i]);
+                }
+                println!("Finished, stats: {:?}", stats);
+                return
+            }
+        }
+    }
+}
+
+#[test]
+fn small_encode_decode_with_gilbert_elliott_loss() {
+    encode_decode_systematic_with_gilbert_elliott_loss(1300, 128);
+}
+
+#[test]
+fn combinations_encode_decode_with_gilbert_elliott_loss() {
+    for size in 1000..1100 {
+        for chunk in 100..130 {
+            encode_decode_systematic_with_gilbert_elliott_loss(size, chunk);
+        }
+    }
+}
+
+#[test]
+fn encoder_with_seed_differs_by_seed() {
+    let s: String = thread_rng().gen_ascii_chars().take(1024).collect();
+    let buf = s.into_bytes();
+
+    let first: Vec<_> = Encoder::with_seed(buf.clone(), 64, EncoderType::Random, SolitonType::Ideal, 1).take(5).collect();
+    let second: Vec<_> = Encoder::with_seed(buf, 64, EncoderType::Random, SolitonType::Ideal, 2).take(5).collect();
+    assert_ne!(format!("{:?}", first), format!("{:?}", second));
+}
+
+// `os_seed()` has no OS entropy to draw on under `no_std` and always
+// returns the same placeholder, so `Encoder::new`/`ObjectEncoder::new`
+// alone would produce identical droplet streams on every run. This test
+// is gated on `not(feature = "std")` so it only exercises that build
+// (`cargo test --no-default-features`); the default `cargo test` run
+// never compiles it, matching how the rest of this suite never runs
+// against the `no_std` configuration either.
+#[cfg(not(feature = "std"))]
+#[test]
+fn no_std_object_encoder_blocks_get_distinct_seeds() {
+    let data = || (0..256).map(|i| i as u8).collect::<Vec<u8>>();
+
+    // Same base seed -> same per-block derived seeds -> reproducible
+    // stream, so the derivation itself is deterministic.
+    let a: Vec<_> = ObjectEncoder::with_seed(data(), 32, 4, EncoderType::Random, SolitonType::Ideal, 7).take(8).collect();
+    let b: Vec<_> = ObjectEncoder::with_seed(data(), 32, 4, EncoderType::Random, SolitonType::Ideal, 7).take(8).collect();
+    assert_eq!(format!("{:?}", a), format!("{:?}", b));
+
+    // Distinct base seeds must not collapse onto the same stream, which
+    // is what happened when every block reused `os_seed()`'s fixed
+    // placeholder directly.
+    let c: Vec<_> = ObjectEncoder::with_seed(data(), 32, 4, EncoderType::Random, SolitonType::Ideal, 8).take(8).collect();
+    assert_ne!(format!("{:?}", a), format!("{:?}", c));
+}